@@ -5,12 +5,56 @@ use std::{
     io::{self, Read, Seek},
 };
 
-use merkle_tree::{hasher, tree};
+use merkle_tree::{
+    hasher::{self, MerkleTreeHasher},
+    store::DiskStore,
+    tree,
+};
 
-type Hasher = hasher::SdbmHasher;
+// Real roots need a collision-resistant hasher - SdbmHasher is fine for the unit tests
+// elsewhere in the crate, but a file server's root hash is exactly the tamper-evident
+// identifier `Sha256Hasher`/`Keccak256Hasher` exist for.
+type Hasher = hasher::Sha256Hasher;
 type Hash = <Hasher as hasher::MerkleTreeHasher>::Hash;
 
 pub type Tree = tree::MerkleTree<Hasher>;
+type DiskTree = tree::MerkleTree<Hasher, DiskStore<Hash>>;
+
+/// Files with more chunks than this are hashed into a `DiskStore`-backed tree instead of an
+/// in-memory one, so the server doesn't have to hold a full array of node hashes per large
+/// file.
+const DISK_BACKED_CHUNK_THRESHOLD: usize = 1_000_000;
+
+/// Either of the two tree backends a file can be hashed into, so `FileServer` can hold a mix
+/// of small, fully in-memory trees and large, disk-backed ones.
+#[derive(Debug)]
+enum FileTree {
+    Resident(Tree),
+    Paged(DiskTree),
+}
+
+impl FileTree {
+    fn get_root(&self) -> Option<Hash> {
+        match self {
+            FileTree::Resident(tree) => tree.get_root(),
+            FileTree::Paged(tree) => tree.get_root(),
+        }
+    }
+
+    fn get_proof(&self, item_index: usize) -> Option<tree::Proof<Hasher>> {
+        match self {
+            FileTree::Resident(tree) => tree.get_proof(item_index),
+            FileTree::Paged(tree) => tree.get_proof(item_index),
+        }
+    }
+
+    fn get_multiproof(&self, item_indices: &[usize]) -> Option<tree::MultiProof<Hasher>> {
+        match self {
+            FileTree::Resident(tree) => tree.get_multiproof(item_indices),
+            FileTree::Paged(tree) => tree.get_multiproof(item_indices),
+        }
+    }
+}
 
 fn read_file_chunk<File: Read>(file: &mut File, chunk_size: usize) -> io::Result<Vec<u8>> {
     let mut bytes_read = 0;
@@ -26,6 +70,13 @@ fn read_file_chunk<File: Read>(file: &mut File, chunk_size: usize) -> io::Result
     Ok(buffer)
 }
 
+/// Hashes a chunk of file data the same way the server's trees do, so a caller holding a
+/// [`tree::MultiProof`] from [`FileServer::get_file_chunks`] can build the `(index, hash)` pairs
+/// [`tree::MerkleTree::verify_multiproof`] needs without reaching into the server's hasher choice.
+pub fn hash_chunk(data: &[u8]) -> Hash {
+    Hasher::hash(data)
+}
+
 fn read_file_chunk_by_offet(
     file_path: &std::path::PathBuf,
     offset: u64,
@@ -39,7 +90,7 @@ fn read_file_chunk_by_offet(
 fn make_merkle_tree_for_file(
     file_path: &std::path::PathBuf,
     chunk_size: usize,
-) -> Result<(Tree, u64), String> {
+) -> Result<(FileTree, u64), String> {
     let mut file =
         fs::File::open(file_path).map_err(|_| format!("can not open file {:?}", file_path))?;
 
@@ -52,20 +103,45 @@ fn make_merkle_tree_for_file(
         .try_into()
         .map_err(|_| format!("can not calculate chunks number for file {:?}", file_path))?;
 
-    Ok((
-        Tree::try_from_data_items(
-            (0..chunks_number).map(|_| read_file_chunk(&mut file, chunk_size)),
+    let tree = if chunks_number > DISK_BACKED_CHUNK_THRESHOLD {
+        // Append rather than replace the extension - `with_extension` would collapse
+        // same-stem files like `report.txt` and `report.csv` onto the same `report.merkle`
+        // store, letting one truncate the other's still-live backing file.
+        let mut store_file_name = file_path
+            .file_name()
+            .ok_or_else(|| format!("file {:?} has no file name", file_path))?
+            .to_os_string();
+        store_file_name.push(".merkle");
+        let store_path = file_path.with_file_name(store_file_name);
+        let store = DiskStore::create(&store_path)
+            .map_err(|_| format!("can not create node store for file {:?}", file_path))?;
+
+        FileTree::Paged(
+            DiskTree::try_from_hash_items_with_store(
+                (0..chunks_number).map(|_| {
+                    read_file_chunk(&mut file, chunk_size).map(|data| Hasher::hash(&data))
+                }),
+                store,
+            )
+            .map_err(|_| format!("can not read file {:?}", file_path))?,
+        )
+    } else {
+        FileTree::Resident(
+            Tree::try_from_data_items(
+                (0..chunks_number).map(|_| read_file_chunk(&mut file, chunk_size)),
+            )
+            .map_err(|_| format!("can not read file {:?}", file_path))?,
         )
-        .map_err(|_| format!("can not read file {:?}", file_path))?,
-        file_size,
-    ))
+    };
+
+    Ok((tree, file_size))
 }
 
 #[derive(Debug)]
 struct FileHash {
     path: std::path::PathBuf,
     size: u64,
-    tree: Tree,
+    tree: FileTree,
 }
 
 #[derive(Debug)]
@@ -187,4 +263,33 @@ impl FileServer {
             _ => None,
         }
     }
+
+    /// Like [`Self::get_file_chunk`], but proves several chunks at once with a single
+    /// [`tree::MultiProof`] instead of one independent proof per chunk - for a contiguous range
+    /// this is a lot fewer sibling hashes than `chunk_indices.len()` calls to `get_file_chunk`
+    /// would send.
+    pub fn get_file_chunks(
+        &self,
+        file_hash: Hash,
+        chunk_indices: &[usize],
+    ) -> Option<(tree::MultiProof<Hasher>, Vec<Vec<u8>>)> {
+        let file_info = self.files.get(&file_hash)?;
+
+        let chunks_data = chunk_indices
+            .iter()
+            .map(|&chunk_index| {
+                read_file_chunk_by_offet(
+                    &file_info.path,
+                    chunk_index as u64 * self.chunk_size as u64,
+                    self.chunk_size,
+                )
+                .ok()
+                .filter(|data| !data.is_empty())
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        let proof = file_info.tree.get_multiproof(chunk_indices)?;
+
+        Some((proof, chunks_data))
+    }
 }
@@ -1,6 +1,6 @@
 mod file_server;
 
-use file_server::{FileServer, Tree};
+use file_server::{hash_chunk, FileServer, Tree};
 
 fn run() -> Result<(), String> {
     let data_dir_path = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("data");
@@ -14,7 +14,7 @@ fn run() -> Result<(), String> {
     files.iter().for_each(|file| println!("{:#?}", file));
 
     let file_index = 1;
-    let chunk_index = 5;
+    let chunk_index = 0;
 
     let file_root_hash = *files
         .get(file_index)
@@ -34,6 +34,36 @@ fn run() -> Result<(), String> {
         if is_chunk_valid { "VALID" } else { "INVALID" }
     );
 
+    // A client that wants every chunk of a file (rather than just one) is better served by a
+    // single multiproof over the whole range than by one independent proof per chunk.
+    let chunk_count =
+        (files[file_index].size).div_ceil(files[file_index].chunk_size as u64) as usize;
+    let chunk_indices: Vec<usize> = (0..chunk_count).collect();
+
+    let (chunks_proof, chunks_data) = server
+        .get_file_chunks(file_root_hash, &chunk_indices)
+        .ok_or_else(|| {
+            format!(
+                "chunks 0..{} not found in file #{}",
+                chunk_count, file_index
+            )
+        })?;
+
+    let leaves: Vec<_> = chunk_indices
+        .iter()
+        .zip(chunks_data.iter())
+        .map(|(&index, data)| (index, hash_chunk(data)))
+        .collect();
+
+    let are_chunks_valid = Tree::verify_multiproof(&leaves, file_root_hash, &chunks_proof);
+
+    println!(
+        "\nChunks 0..{} of file #{} are {}",
+        chunk_count,
+        file_index,
+        if are_chunks_valid { "VALID" } else { "INVALID" }
+    );
+
     Ok(())
 }
 
@@ -1,29 +1,82 @@
-trait SimpleHasher {
+use sha2::{Digest, Sha256};
+use sha3::Keccak256;
+
+pub trait SimpleHasher {
     type Hash: PartialEq + Copy + std::fmt::Debug;
 
     fn hash(data: &[u8]) -> Self::Hash;
 }
 
 #[derive(PartialEq, Eq, Clone, Copy, Hash)]
-pub struct Hash64(u64);
+pub struct Hash64([u8; 8]);
 
 impl Hash64 {
     pub fn new(inner: u64) -> Self {
+        Self(inner.to_le_bytes())
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        let mut inner = [0u8; 8];
+        inner.copy_from_slice(bytes);
         Self(inner)
     }
 
     #[inline]
     pub fn inner(&self) -> u64 {
-        self.0
+        u64::from_le_bytes(self.0)
+    }
+}
+
+impl AsRef<[u8]> for Hash64 {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
     }
 }
 
 impl std::fmt::Debug for Hash64 {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{:016x?}", self.0)
+        fmt_hex(&self.0, f)
+    }
+}
+
+/// A fixed-width hash of `N` bytes, used by the cryptographic hashers below.
+#[derive(PartialEq, Eq, Clone, Copy, Hash)]
+pub struct HashBytes<const N: usize>([u8; N]);
+
+impl<const N: usize> HashBytes<N> {
+    pub fn new(inner: [u8; N]) -> Self {
+        Self(inner)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        let mut inner = [0u8; N];
+        inner.copy_from_slice(bytes);
+        Self(inner)
+    }
+}
+
+impl<const N: usize> AsRef<[u8]> for HashBytes<N> {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl<const N: usize> std::fmt::Debug for HashBytes<N> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        fmt_hex(&self.0, f)
     }
 }
 
+/// The hash width produced by [`Sha256Hasher`] and [`Keccak256Hasher`].
+pub type Hash256 = HashBytes<32>;
+
+fn fmt_hex(bytes: &[u8], f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    for byte in bytes {
+        write!(f, "{:02x}", byte)?;
+    }
+    Ok(())
+}
+
 #[derive(Debug)]
 pub struct Djb2Hasher;
 
@@ -31,7 +84,7 @@ impl SimpleHasher for Djb2Hasher {
     type Hash = Hash64;
 
     fn hash(data: &[u8]) -> Hash64 {
-        Hash64(data.iter().fold(5381, |hash, c| {
+        Hash64::new(data.iter().fold(5381, |hash, c| {
             (hash << 5).wrapping_add(hash).wrapping_add(*c as u64) // hash * 33 + c
         }))
     }
@@ -44,7 +97,7 @@ impl SimpleHasher for SdbmHasher {
     type Hash = Hash64;
 
     fn hash(data: &[u8]) -> Hash64 {
-        Hash64(data.iter().fold(0, |hash, c| {
+        Hash64::new(data.iter().fold(0, |hash, c| {
             (*c as u64)
                 .wrapping_add(hash << 6)
                 .wrapping_add(hash << 16)
@@ -53,25 +106,92 @@ impl SimpleHasher for SdbmHasher {
     }
 }
 
+/// SHA-256, as specified in FIPS 180-4.
+#[derive(Debug)]
+pub struct Sha256Hasher;
+
+impl SimpleHasher for Sha256Hasher {
+    type Hash = Hash256;
+
+    fn hash(data: &[u8]) -> Hash256 {
+        HashBytes::new(Sha256::digest(data).into())
+    }
+}
+
+/// Keccak-256, the hash function used by Ethereum (not NIST SHA3-256).
+#[derive(Debug)]
+pub struct Keccak256Hasher;
+
+impl SimpleHasher for Keccak256Hasher {
+    type Hash = Hash256;
+
+    fn hash(data: &[u8]) -> Hash256 {
+        HashBytes::new(Keccak256::digest(data).into())
+    }
+}
+
+/// Selects a cryptographic hash algorithm at runtime.
+///
+/// Both variants produce a [`Hash256`], so callers that need to pick the algorithm
+/// dynamically (e.g. from configuration) can use this instead of choosing between
+/// [`Sha256Hasher`] and [`Keccak256Hasher`] at compile time.
+///
+/// # Examples
+///
+/// ```
+/// use merkle_tree::hasher::{HashType, SimpleHasher, Sha256Hasher};
+///
+/// let hash = HashType::Sha256.hash(b"hello");
+/// assert_eq!(hash, <Sha256Hasher as SimpleHasher>::hash(b"hello"));
+/// assert_ne!(HashType::Sha256.hash(b"hello"), HashType::Keccak256.hash(b"hello"));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashType {
+    Sha256,
+    Keccak256,
+}
+
+impl HashType {
+    pub fn hash(self, data: &[u8]) -> Hash256 {
+        match self {
+            HashType::Sha256 => <Sha256Hasher as SimpleHasher>::hash(data),
+            HashType::Keccak256 => <Keccak256Hasher as SimpleHasher>::hash(data),
+        }
+    }
+}
+
 pub trait MerkleTreeHasher {
-    type Hash: PartialEq + Copy + std::fmt::Debug;
+    type Hash: PartialEq + Copy + AsRef<[u8]> + std::fmt::Debug;
 
     fn hash(data: &[u8]) -> Self::Hash;
-    fn concat(hash1: Self::Hash, hash2: Self::Hash) -> Self::Hash;
+
+    /// Hashes the concatenation of an arbitrary number of child hashes, generalizing `concat`
+    /// to trees with more than two branches per node.
+    fn concat_many(hashes: &[Self::Hash]) -> Self::Hash;
+
+    fn concat(hash1: Self::Hash, hash2: Self::Hash) -> Self::Hash {
+        Self::concat_many(&[hash1, hash2])
+    }
 }
 
 impl<Hasher> MerkleTreeHasher for Hasher
 where
-    Hasher: SimpleHasher<Hash = Hash64>,
+    Hasher: SimpleHasher,
+    Hasher::Hash: AsRef<[u8]>,
 {
-    type Hash = Hash64;
+    type Hash = Hasher::Hash;
 
-    fn hash(data: &[u8]) -> Hash64 {
+    fn hash(data: &[u8]) -> Self::Hash {
         Self::hash(data)
     }
 
-    fn concat(hash1: Hash64, hash2: Hash64) -> Hash64 {
-        Self::hash(&((hash1.0 as u128) << 64 | (hash2.0 as u128)).to_le_bytes())
+    fn concat_many(hashes: &[Self::Hash]) -> Self::Hash {
+        let mut concatenated =
+            Vec::with_capacity(hashes.iter().map(|hash| hash.as_ref().len()).sum());
+        for hash in hashes {
+            concatenated.extend_from_slice(hash.as_ref());
+        }
+        Self::hash(&concatenated)
     }
 }
 
@@ -80,11 +200,11 @@ fn test_dbj2() {
     let hash1 = <Djb2Hasher as SimpleHasher>::hash("hello".as_bytes());
     let hash2 = <Djb2Hasher as SimpleHasher>::hash("world".as_bytes());
 
-    assert_eq!(hash1, Hash64(0x0000_0031_0F92_3099));
-    assert_eq!(hash2, Hash64(0x0000_0031_10A7_356D));
+    assert_eq!(hash1, Hash64::new(0x0000_0031_0F92_3099));
+    assert_eq!(hash2, Hash64::new(0x0000_0031_10A7_356D));
     assert_eq!(
         Djb2Hasher::concat(hash1, hash2),
-        Hash64(0xE9B2_0141_B1A0_810A)
+        Hash64::new(0xE72A_5AC6_855B_220A)
     );
 }
 
@@ -93,10 +213,58 @@ fn test_sdbm() {
     let hash1 = <SdbmHasher as SimpleHasher>::hash("hello".as_bytes());
     let hash2 = <SdbmHasher as SimpleHasher>::hash("world".as_bytes());
 
-    assert_eq!(hash1, Hash64(0x66EB_1BB3_28D1_9932));
-    assert_eq!(hash2, Hash64(0x75BE_975B_F7E3_AEB2));
+    assert_eq!(hash1, Hash64::new(0x66EB_1BB3_28D1_9932));
+    assert_eq!(hash2, Hash64::new(0x75BE_975B_F7E3_AEB2));
     assert_eq!(
         SdbmHasher::concat(hash1, hash2),
-        Hash64(0x8108_4122_AFDB_AAE4)
+        Hash64::new(0xEA21_C124_3F8C_6EE4)
+    );
+}
+
+#[test]
+fn test_sha256() {
+    let hash1 = <Sha256Hasher as SimpleHasher>::hash("hello".as_bytes());
+    let hash2 = <Sha256Hasher as SimpleHasher>::hash("world".as_bytes());
+
+    assert_ne!(hash1, hash2);
+    assert_eq!(
+        Sha256Hasher::concat(hash1, hash1),
+        Sha256Hasher::concat(hash1, hash1)
+    );
+    assert_ne!(
+        Sha256Hasher::concat(hash1, hash2),
+        Sha256Hasher::concat(hash2, hash1)
+    );
+}
+
+#[test]
+fn test_keccak256() {
+    let hash1 = <Keccak256Hasher as SimpleHasher>::hash("hello".as_bytes());
+    let hash2 = <Keccak256Hasher as SimpleHasher>::hash("world".as_bytes());
+
+    assert_ne!(hash1, hash2);
+    assert_ne!(
+        <Keccak256Hasher as SimpleHasher>::hash("hello".as_bytes()).as_ref(),
+        <Sha256Hasher as SimpleHasher>::hash("hello".as_bytes()).as_ref()
+    );
+}
+
+#[test]
+fn test_hash_type() {
+    let hash = HashType::Sha256.hash("hello".as_bytes());
+    assert_eq!(
+        hash,
+        <Sha256Hasher as SimpleHasher>::hash("hello".as_bytes())
+    );
+
+    let hash = HashType::Keccak256.hash("hello".as_bytes());
+    assert_eq!(
+        hash,
+        <Keccak256Hasher as SimpleHasher>::hash("hello".as_bytes())
+    );
+
+    assert_ne!(
+        HashType::Sha256.hash("hello".as_bytes()),
+        HashType::Keccak256.hash("hello".as_bytes())
     );
 }
@@ -0,0 +1,272 @@
+use std::collections::HashMap;
+
+use crate::hasher::MerkleTreeHasher;
+
+/// One step of a [`SparseProof`]: the sibling hash at a given level, and whether that sibling
+/// was an empty (default) subtree rather than something actually inserted.
+#[derive(Debug, Clone, Copy)]
+pub struct SparseProofStep<Hasher: MerkleTreeHasher> {
+    pub sibling_hash: Hasher::Hash,
+    pub is_default: bool,
+}
+
+/// A proof for a [`SparseMerkleTree`], ordered from the leaf's sibling up to the root's.
+pub type SparseProof<Hasher> = Vec<SparseProofStep<Hasher>>;
+
+/// A sparse Merkle tree keyed by arbitrary byte-string identifiers.
+///
+/// Unlike [`crate::tree::MerkleTree`], leaves live at a position derived from `Hasher::hash(key)`
+/// rather than a sequential index, and the astronomically large majority of the key space is
+/// never touched: untouched subtrees collapse to precomputed default hashes (`default[0] =
+/// hash(&[])`, `default[i] = concat(default[i - 1], default[i - 1])`), so the root of an
+/// all-empty tree - and the cost of proving a key absent - is `O(depth)` rather than `O(2^depth)`.
+#[derive(Debug)]
+pub struct SparseMerkleTree<Hasher: MerkleTreeHasher> {
+    depth: usize,
+
+    // default[level] is the hash of an empty subtree of that level's height (0 = leaf level).
+    defaults: Vec<Hasher::Hash>,
+
+    // Non-default nodes only, keyed by (level, the key-path bits identifying the node at that
+    // level, read from the root down).
+    nodes: HashMap<(usize, Vec<bool>), Hasher::Hash>,
+
+    root: Hasher::Hash,
+}
+
+impl<Hasher: MerkleTreeHasher> SparseMerkleTree<Hasher> {
+    /// Creates an empty tree of the given `depth` (number of key bits used to address a leaf).
+    /// `depth` must not exceed the bit width of `Hasher`'s hash output.
+    pub fn new(depth: usize) -> Self {
+        let defaults = Self::compute_defaults(depth);
+
+        assert!(
+            depth <= defaults[0].as_ref().len() * 8,
+            "SparseMerkleTree: depth ({depth}) exceeds the hash width"
+        );
+
+        let root = defaults[depth];
+
+        Self {
+            depth,
+            defaults,
+            nodes: HashMap::new(),
+            root,
+        }
+    }
+
+    pub fn get_root(&self) -> Hasher::Hash {
+        self.root
+    }
+
+    pub fn insert(&mut self, key: &[u8], value: &[u8]) {
+        let path_bits = self.key_path(key);
+        let mut hash = Hasher::hash(value);
+
+        self.nodes.insert((0, path_bits.clone()), hash);
+
+        for level in 0..self.depth {
+            let prefix_len = self.depth - level;
+            let bit = path_bits[prefix_len - 1];
+
+            let sibling_prefix = Self::flip_last_bit(&path_bits[..prefix_len]);
+            let sibling_hash = self
+                .nodes
+                .get(&(level, sibling_prefix))
+                .copied()
+                .unwrap_or(self.defaults[level]);
+
+            hash = if bit {
+                Hasher::concat(sibling_hash, hash)
+            } else {
+                Hasher::concat(hash, sibling_hash)
+            };
+
+            self.nodes
+                .insert((level + 1, path_bits[..prefix_len - 1].to_vec()), hash);
+        }
+
+        self.root = hash;
+    }
+
+    pub fn get_proof(&self, key: &[u8]) -> SparseProof<Hasher> {
+        let path_bits = self.key_path(key);
+        let mut proof = SparseProof::<Hasher>::with_capacity(self.depth);
+
+        for level in 0..self.depth {
+            let prefix_len = self.depth - level;
+            let sibling_prefix = Self::flip_last_bit(&path_bits[..prefix_len]);
+
+            let stored = self.nodes.get(&(level, sibling_prefix));
+
+            proof.push(SparseProofStep {
+                sibling_hash: stored.copied().unwrap_or(self.defaults[level]),
+                is_default: stored.is_none(),
+            });
+        }
+
+        proof
+    }
+
+    pub fn verify_proof(
+        key: &[u8],
+        value: &[u8],
+        root_hash: Hasher::Hash,
+        proof: &SparseProof<Hasher>,
+    ) -> bool {
+        let key_hash = Hasher::hash(key);
+        if !Self::fits_hash_width(&key_hash, proof.len()) {
+            return false;
+        }
+
+        let path_bits = Self::bits_from_hash(&key_hash, proof.len());
+        Self::calc_proof_hash(&path_bits, Hasher::hash(value), proof) == root_hash
+    }
+
+    /// Proves that `key` is absent, i.e. that its leaf still holds the empty default hash.
+    pub fn verify_non_membership(
+        key: &[u8],
+        root_hash: Hasher::Hash,
+        proof: &SparseProof<Hasher>,
+    ) -> bool {
+        let key_hash = Hasher::hash(key);
+        if !Self::fits_hash_width(&key_hash, proof.len()) {
+            return false;
+        }
+
+        let path_bits = Self::bits_from_hash(&key_hash, proof.len());
+        Self::calc_proof_hash(&path_bits, Hasher::hash(&[]), proof) == root_hash
+    }
+
+    // Whether `depth` bits can actually be read from `hash` without running off the end of it;
+    // a proof longer than the hasher's output width can't have been produced by a real tree.
+    fn fits_hash_width(hash: &Hasher::Hash, depth: usize) -> bool {
+        depth <= hash.as_ref().len() * 8
+    }
+
+    fn calc_proof_hash(
+        path_bits: &[bool],
+        leaf_hash: Hasher::Hash,
+        proof: &SparseProof<Hasher>,
+    ) -> Hasher::Hash {
+        let depth = proof.len();
+
+        proof
+            .iter()
+            .enumerate()
+            .fold(leaf_hash, |hash, (level, step)| {
+                let bit = path_bits[depth - level - 1];
+                if bit {
+                    Hasher::concat(step.sibling_hash, hash)
+                } else {
+                    Hasher::concat(hash, step.sibling_hash)
+                }
+            })
+    }
+
+    fn key_path(&self, key: &[u8]) -> Vec<bool> {
+        Self::bits_from_hash(&Hasher::hash(key), self.depth)
+    }
+
+    // The first `depth` bits of `hash`, read most-significant-bit first; bit 0 addresses the
+    // root's children, and the last bit addresses a leaf's position among its siblings.
+    fn bits_from_hash(hash: &Hasher::Hash, depth: usize) -> Vec<bool> {
+        let bytes = hash.as_ref();
+        (0..depth)
+            .map(|i| (bytes[i / 8] >> (7 - (i % 8))) & 1 == 1)
+            .collect()
+    }
+
+    fn flip_last_bit(prefix: &[bool]) -> Vec<bool> {
+        let mut flipped = prefix.to_vec();
+        let last = flipped.len() - 1;
+        flipped[last] = !flipped[last];
+        flipped
+    }
+
+    fn compute_defaults(depth: usize) -> Vec<Hasher::Hash> {
+        let mut defaults = Vec::with_capacity(depth + 1);
+        defaults.push(Hasher::hash(&[]));
+
+        for i in 1..=depth {
+            let previous = defaults[i - 1];
+            defaults.push(Hasher::concat(previous, previous));
+        }
+
+        defaults
+    }
+}
+
+#[test]
+fn test_sparse_tree() {
+    type Tree = SparseMerkleTree<crate::hasher::SdbmHasher>;
+
+    let mut tree = Tree::new(16);
+
+    let empty_root = tree.get_root();
+
+    // A key nobody has inserted yet is provably absent.
+    let proof = tree.get_proof(b"absent-key");
+    assert!(proof.iter().all(|step| step.is_default));
+    assert!(Tree::verify_non_membership(
+        b"absent-key",
+        empty_root,
+        &proof
+    ));
+
+    tree.insert(b"alice", b"100");
+    tree.insert(b"bob", b"200");
+
+    let root = tree.get_root();
+    assert_ne!(root, empty_root);
+
+    let proof = tree.get_proof(b"alice");
+    assert!(Tree::verify_proof(b"alice", b"100", root, &proof));
+    assert!(!Tree::verify_proof(b"alice", b"999", root, &proof));
+    assert!(!Tree::verify_proof(b"bob", b"100", root, &proof));
+
+    let proof = tree.get_proof(b"bob");
+    assert!(Tree::verify_proof(b"bob", b"200", root, &proof));
+
+    // A key that was never inserted is still provably absent after other keys were inserted.
+    let proof = tree.get_proof(b"carol");
+    assert!(Tree::verify_non_membership(b"carol", root, &proof));
+    assert!(!Tree::verify_proof(b"carol", b"anything", root, &proof));
+
+    // Re-inserting a key updates its leaf and the root.
+    tree.insert(b"alice", b"150");
+    let updated_root = tree.get_root();
+    assert_ne!(updated_root, root);
+
+    let proof = tree.get_proof(b"alice");
+    assert!(Tree::verify_proof(b"alice", b"150", updated_root, &proof));
+    assert!(!Tree::verify_proof(b"alice", b"100", updated_root, &proof));
+}
+
+#[test]
+fn test_sparse_tree_oversized_proof() {
+    type Tree = SparseMerkleTree<crate::hasher::SdbmHasher>;
+
+    // SdbmHasher produces a 64-bit (8-byte) hash, so a 100-step proof can't have come from a
+    // real tree over this hasher; verification must reject it instead of panicking trying to
+    // read past the end of the key's hash.
+    let oversized_proof: SparseProof<crate::hasher::SdbmHasher> = (0..100)
+        .map(|_| SparseProofStep {
+            sibling_hash: crate::hasher::SdbmHasher::hash(&[]),
+            is_default: true,
+        })
+        .collect();
+
+    let root = crate::hasher::SdbmHasher::hash(&[]);
+    assert!(!Tree::verify_proof(
+        b"anything",
+        b"value",
+        root,
+        &oversized_proof
+    ));
+    assert!(!Tree::verify_non_membership(
+        b"anything",
+        root,
+        &oversized_proof
+    ));
+}
@@ -0,0 +1,267 @@
+use std::{
+    fs,
+    io::{Read, Seek, SeekFrom, Write},
+    marker::PhantomData,
+    path::Path,
+};
+
+/// A hash type that can be serialized to, and reconstructed from, a fixed-width byte buffer.
+///
+/// This is what lets a [`DiskStore`] write hashes to a flat file and read them back,
+/// regardless of which `MerkleTreeHasher` produced them.
+pub trait FixedSizeHash: Copy {
+    const SIZE: usize;
+
+    fn to_bytes(&self) -> Vec<u8>;
+    fn from_bytes(bytes: &[u8]) -> Self;
+}
+
+impl FixedSizeHash for crate::hasher::Hash64 {
+    const SIZE: usize = 8;
+
+    fn to_bytes(&self) -> Vec<u8> {
+        self.as_ref().to_vec()
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Self {
+        Self::from_bytes(bytes)
+    }
+}
+
+impl<const N: usize> FixedSizeHash for crate::hasher::HashBytes<N> {
+    const SIZE: usize = N;
+
+    fn to_bytes(&self) -> Vec<u8> {
+        self.as_ref().to_vec()
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Self {
+        Self::from_bytes(bytes)
+    }
+}
+
+/// An abstraction over the flat array of node hashes backing a `MerkleTree`, so the tree can
+/// be held fully in memory ([`VecStore`]), entirely on disk ([`DiskStore`]), or as a hybrid of
+/// the two ([`LevelCacheStore`]).
+pub trait Store<Hash> {
+    fn push(&mut self, hash: Hash);
+    fn set(&mut self, index: usize, hash: Hash);
+    fn get(&self, index: usize) -> Option<Hash>;
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// The default, in-memory `Store`, backed by a `Vec`.
+#[derive(Debug)]
+pub struct VecStore<Hash>(Vec<Hash>);
+
+impl<Hash> Default for VecStore<Hash> {
+    fn default() -> Self {
+        Self(Vec::new())
+    }
+}
+
+impl<Hash: Copy> Store<Hash> for VecStore<Hash> {
+    fn push(&mut self, hash: Hash) {
+        self.0.push(hash);
+    }
+
+    fn set(&mut self, index: usize, hash: Hash) {
+        self.0[index] = hash;
+    }
+
+    fn get(&self, index: usize) -> Option<Hash> {
+        self.0.get(index).copied()
+    }
+
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+}
+
+/// A `Store` that keeps every node hash in a flat file instead of in memory, so a tree over a
+/// huge number of leaves doesn't have to fit in RAM. Proof generation only ever reads one node
+/// per level, so this costs one seek+read per level rather than loading the whole file.
+///
+/// `Store::push`/`set` return `()`, so a seek or write failure (e.g. a full disk or revoked
+/// permissions) has nowhere to go but a panic - unlike `get`, which can report failure by
+/// returning `None`. Callers that need writes to be infallible should make sure the backing
+/// file is writable before handing it to a `DiskStore`.
+#[derive(Debug)]
+pub struct DiskStore<Hash: FixedSizeHash> {
+    file: fs::File,
+    len: usize,
+    _hash: PhantomData<Hash>,
+}
+
+impl<Hash: FixedSizeHash> DiskStore<Hash> {
+    /// Creates a new, empty disk-backed store at `path`, truncating any existing file.
+    pub fn create<P: AsRef<Path>>(path: P) -> std::io::Result<Self> {
+        let file = fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)?;
+
+        Ok(Self {
+            file,
+            len: 0,
+            _hash: PhantomData,
+        })
+    }
+
+    /// Opens an existing disk-backed store at `path`, which already holds `len` hashes.
+    pub fn open<P: AsRef<Path>>(path: P, len: usize) -> std::io::Result<Self> {
+        let file = fs::OpenOptions::new().read(true).write(true).open(path)?;
+
+        Ok(Self {
+            file,
+            len,
+            _hash: PhantomData,
+        })
+    }
+
+    fn offset(index: usize) -> u64 {
+        (index * Hash::SIZE) as u64
+    }
+}
+
+impl<Hash: FixedSizeHash> Store<Hash> for DiskStore<Hash> {
+    fn push(&mut self, hash: Hash) {
+        self.set(self.len, hash);
+        self.len += 1;
+    }
+
+    fn set(&mut self, index: usize, hash: Hash) {
+        (&self.file)
+            .seek(SeekFrom::Start(Self::offset(index)))
+            .expect("DiskStore: seek failed");
+        (&self.file)
+            .write_all(&hash.to_bytes())
+            .expect("DiskStore: write failed");
+    }
+
+    fn get(&self, index: usize) -> Option<Hash> {
+        if index >= self.len {
+            return None;
+        }
+
+        (&self.file)
+            .seek(SeekFrom::Start(Self::offset(index)))
+            .ok()?;
+
+        let mut buffer = vec![0u8; Hash::SIZE];
+        (&self.file).read_exact(&mut buffer).ok()?;
+
+        Some(Hash::from_bytes(&buffer))
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+}
+
+/// A `Store` that keeps only the top levels of a tree resident in memory (in a [`VecStore`])
+/// and spills everything below `cache_start_index` to a [`DiskStore`]. Nodes are addressed by
+/// the same flat, reversed-breadth-first index `MerkleTree` already uses, so the tree doesn't
+/// need to know which backend actually holds a given node.
+#[derive(Debug)]
+pub struct LevelCacheStore<Hash: FixedSizeHash> {
+    disk: DiskStore<Hash>,
+    cache: VecStore<Hash>,
+    cache_start_index: usize,
+}
+
+impl<Hash: FixedSizeHash> LevelCacheStore<Hash> {
+    pub fn new(disk: DiskStore<Hash>, cache_start_index: usize) -> Self {
+        Self {
+            disk,
+            cache: VecStore::default(),
+            cache_start_index,
+        }
+    }
+}
+
+impl<Hash: FixedSizeHash + Copy> Store<Hash> for LevelCacheStore<Hash> {
+    fn push(&mut self, hash: Hash) {
+        if self.disk.len() < self.cache_start_index {
+            self.disk.push(hash);
+        } else {
+            self.cache.push(hash);
+        }
+    }
+
+    fn set(&mut self, index: usize, hash: Hash) {
+        if index < self.cache_start_index {
+            self.disk.set(index, hash);
+        } else {
+            self.cache.set(index - self.cache_start_index, hash);
+        }
+    }
+
+    fn get(&self, index: usize) -> Option<Hash> {
+        if index < self.cache_start_index {
+            self.disk.get(index)
+        } else {
+            self.cache.get(index - self.cache_start_index)
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.disk.len() + self.cache.len()
+    }
+}
+
+#[test]
+fn test_level_cache_store() {
+    use crate::{
+        hasher::{Hash64, MerkleTreeHasher, SdbmHasher},
+        tree::MerkleTree,
+    };
+
+    type Tree = MerkleTree<SdbmHasher>;
+    type CachedTree = MerkleTree<SdbmHasher, LevelCacheStore<Hash64>>;
+
+    let items = ["hello", "world", "foo", "bar", "baz", "qux", "quux"];
+
+    let reference_tree = Tree::from_data_items(items.iter());
+    let root_hash = reference_tree.get_root().unwrap();
+
+    let path = std::env::temp_dir().join(format!(
+        "merkle_tree_test_level_cache_store_{:?}",
+        std::thread::current().id()
+    ));
+
+    let disk = DiskStore::create(&path).unwrap();
+    // Leaves spill to disk; every level above them stays resident in the in-memory cache, so a
+    // proof reads only its leaf-level sibling from disk.
+    let store = LevelCacheStore::new(disk, items.len());
+
+    let tree = CachedTree::try_from_hash_items_with_store(
+        items
+            .iter()
+            .map(|item| Ok::<_, ()>(SdbmHasher::hash(item.as_bytes()))),
+        store,
+    )
+    .unwrap();
+
+    assert_eq!(tree.get_root().unwrap(), root_hash);
+
+    for (item_index, item_data) in items.iter().enumerate() {
+        let proof = tree.get_proof(item_index).unwrap();
+        assert!(CachedTree::verify_proof(
+            item_data.as_bytes(),
+            root_hash,
+            &proof
+        ));
+
+        // The proof must match what an all-in-memory tree over the same data would produce.
+        assert_eq!(proof, reference_tree.get_proof(item_index).unwrap());
+    }
+
+    std::fs::remove_file(&path).ok();
+}
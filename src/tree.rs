@@ -1,13 +1,30 @@
-use crate::hasher::MerkleTreeHasher;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io::{self, Read, Write},
+    marker::PhantomData,
+};
+
+use crate::{
+    hasher::MerkleTreeHasher,
+    store::{FixedSizeHash, Store, VecStore},
+};
+
+// Version byte written at the start of the formats produced by `write_to` / `write_proof_to`,
+// bumped whenever the on-disk/wire layout changes incompatibly.
+const FORMAT_VERSION: u8 = 1;
 
 /// A Merkle tree data structure.
 ///
-/// A Merkle tree is a binary tree where the leaves are hashes of the data items, and the internal nodes are
-/// hashes of the concatenation of their child nodes. The root node of the tree is the hash of the entire
-/// tree.
+/// A Merkle tree is a tree where the leaves are hashes of the data items, and the internal
+/// nodes are hashes of the concatenation of their child nodes. The root node of the tree is
+/// the hash of the entire tree.
 ///
-/// The `MerkleTree` structure is parameterized by a `MerkleTreeHasher` trait object, which specifies the
-/// hash function used to generate the hashes in the tree.
+/// The `MerkleTree` structure is parameterized by a `MerkleTreeHasher` trait object, which
+/// specifies the hash function used to generate the hashes in the tree; by a `Store`, which
+/// specifies where the node hashes themselves live (in memory, on disk, or a hybrid of the
+/// two - see [`crate::store`]); and by a `BRANCHES` const generic, which controls the tree's
+/// arity (the number of children per internal node). `Store` defaults to [`VecStore`] and
+/// `BRANCHES` defaults to `2`, giving the usual in-memory binary Merkle tree.
 ///
 /// # Examples
 ///
@@ -32,23 +49,43 @@ use crate::hasher::MerkleTreeHasher;
 ///
 /// assert_eq!(tree.get_proof(2), None);
 /// ```
-
 #[derive(Debug)]
-pub struct MerkleTree<Hasher: MerkleTreeHasher> {
+pub struct MerkleTree<
+    Hasher,
+    S = VecStore<<Hasher as MerkleTreeHasher>::Hash>,
+    const BRANCHES: usize = 2,
+> where
+    Hasher: MerkleTreeHasher,
+    S: Store<Hasher::Hash>,
+{
     // The number of data items (i.e. leaves) in the tree.
     item_count: usize,
 
     // The number of levels in the tree.
     level_count: usize,
 
-    // A flat array of the nodes in the tree, stored in reversed breadth-first order
-    // (so the root node is the last element of the vector).
-    nodes: Vec<Hasher::Hash>,
-}
+    // The nodes of the tree, stored in reversed breadth-first order (so the root node is the
+    // last one) in the backing `Store`.
+    nodes: S,
 
-pub type Proof<Hasher> = Vec<(<Hasher as MerkleTreeHasher>::Hash, bool)>;
+    _hasher: PhantomData<Hasher>,
+}
 
-impl<Hasher: MerkleTreeHasher> MerkleTree<Hasher> {
+// Each proof step carries the sibling hashes of a node's group (`BRANCHES - 1` of them) along
+// with the position the node itself occupies within that group.
+pub type Proof<Hasher> = Vec<(Vec<<Hasher as MerkleTreeHasher>::Hash>, usize)>;
+
+// A compressed proof for verifying several leaves at once: the tree's `item_count` (needed to
+// replay the grouping the proof was built against) plus, per level, the sibling hashes that
+// weren't already derivable from a leaf being proven or a hash computed earlier in the walk -
+// each one appears at most once, no matter how many of the requested leaves share it.
+pub type MultiProof<Hasher> = (usize, Vec<Vec<<Hasher as MerkleTreeHasher>::Hash>>);
+
+impl<Hasher, S, const BRANCHES: usize> MerkleTree<Hasher, S, BRANCHES>
+where
+    Hasher: MerkleTreeHasher,
+    S: Store<Hasher::Hash>,
+{
     fn calc_tree_size(item_count: usize) -> (usize, usize) {
         let mut level_count = 0;
         let mut tree_node_count: usize = 0;
@@ -57,7 +94,7 @@ impl<Hasher: MerkleTreeHasher> MerkleTree<Hasher> {
         while level_node_count > 1 {
             level_count += 1;
             tree_node_count += level_node_count;
-            level_node_count = (level_node_count + 1) >> 1;
+            level_node_count = level_node_count.div_ceil(BRANCHES);
         }
 
         // level_node_count is 1 or 0 here (0 only if the tree is empty 0 i.e. in case the data items
@@ -68,7 +105,20 @@ impl<Hasher: MerkleTreeHasher> MerkleTree<Hasher> {
         )
     }
 
-    pub fn try_from_hash_items<Error, Items>(hash_items: Items) -> Result<Self, Error>
+    fn node(&self, index: usize) -> Hasher::Hash {
+        self.nodes
+            .get(index)
+            .expect("MerkleTree: node index out of bounds")
+    }
+
+    /// Builds a tree from pre-hashed leaves, storing its nodes in `store` rather than a
+    /// freshly created default `Store`. This is the entry point for backends such as
+    /// [`crate::store::DiskStore`] that need to be set up (e.g. given a file path) before
+    /// nodes can be pushed into them.
+    pub fn try_from_hash_items_with_store<Error, Items>(
+        hash_items: Items,
+        mut store: S,
+    ) -> Result<Self, Error>
     where
         Items: IntoIterator<Item = Result<Hasher::Hash, Error>>,
         <Items as IntoIterator>::IntoIter: ExactSizeIterator,
@@ -76,32 +126,41 @@ impl<Hasher: MerkleTreeHasher> MerkleTree<Hasher> {
         let iter = hash_items.into_iter();
         let item_count = iter.len();
 
-        let (node_count, level_count) = Self::calc_tree_size(item_count);
-
-        let mut nodes = Vec::<Hasher::Hash>::with_capacity(node_count);
+        let (_, level_count) = Self::calc_tree_size(item_count);
 
         // Add hash items (bottom level of the tree).
         for node in iter {
-            node.map(|node| nodes.push(node))?;
+            store.push(node?);
         }
 
         // Add hashes for upper levels of the tree.
         let mut level_start_index = 0;
-        while nodes.len() - level_start_index > 1 {
-            let level_end_index = nodes.len();
-
-            // Iterate through the current level and calculate the hashes
-            // for the next level.
-            for i in (level_start_index..level_end_index).into_iter().step_by(2) {
-                let node_a = nodes[i];
-                let node_b = if i + 1 < level_end_index {
-                    nodes[i + 1]
-                } else {
-                    // Use the last node in the current level as the "right"
-                    // child if the number of nodes is odd.
-                    nodes[level_end_index - 1]
-                };
-                nodes.push(Hasher::concat(node_a, node_b));
+        while store.len() - level_start_index > 1 {
+            let level_end_index = store.len();
+
+            // Iterate through the current level in groups of BRANCHES nodes and calculate the
+            // hashes for the next level.
+            let mut group_start_index = level_start_index;
+            while group_start_index < level_end_index {
+                let group_end_index = (group_start_index + BRANCHES).min(level_end_index);
+
+                let mut group: Vec<Hasher::Hash> = (group_start_index..group_end_index)
+                    .map(|index| {
+                        store
+                            .get(index)
+                            .expect("MerkleTree: node index out of bounds")
+                    })
+                    .collect();
+
+                // Pad the final group of a level by repeating its last node if the
+                // level's node count doesn't divide evenly by BRANCHES.
+                if let Some(&last_node) = group.last() {
+                    group.resize(BRANCHES, last_node);
+                }
+
+                store.push(Hasher::concat_many(&group));
+
+                group_start_index = group_end_index;
             }
 
             level_start_index = level_end_index;
@@ -110,10 +169,231 @@ impl<Hasher: MerkleTreeHasher> MerkleTree<Hasher> {
         Ok(Self {
             item_count,
             level_count,
-            nodes,
+            nodes: store,
+            _hasher: PhantomData,
         })
     }
 
+    pub fn get_root(&self) -> Option<Hasher::Hash> {
+        if self.nodes.is_empty() {
+            None
+        } else {
+            self.nodes.get(self.nodes.len() - 1)
+        }
+    }
+
+    // Returns the index of the first node of `node_index`'s group at the current level, and the
+    // index one past the last real (unpadded) node in that group.
+    fn group_bounds(node_index: usize, node_count: usize) -> (usize, usize) {
+        let group_start_index = (node_index / BRANCHES) * BRANCHES;
+        let group_end_index = (group_start_index + BRANCHES).min(node_count);
+        (group_start_index, group_end_index)
+    }
+
+    pub fn get_proof(&self, item_index: usize) -> Option<Proof<Hasher>> {
+        if item_index >= self.item_count {
+            return None;
+        }
+        let mut proof = Proof::<Hasher>::with_capacity(self.level_count.saturating_sub(1));
+
+        let mut level_start_index = 0;
+        let mut node_count = self.item_count; // node count in level
+        let mut node_index = item_index; // node index in level
+
+        while node_count > 1 {
+            let (group_start_index, group_end_index) = Self::group_bounds(node_index, node_count);
+            let last_index_in_group = group_end_index - 1;
+            let position_in_group = node_index - group_start_index;
+
+            let siblings = (group_start_index..group_start_index + BRANCHES)
+                .filter(|&sibling_index| sibling_index != node_index)
+                .map(|sibling_index| {
+                    self.node(level_start_index + sibling_index.min(last_index_in_group))
+                })
+                .collect();
+
+            proof.push((siblings, position_in_group));
+
+            level_start_index += node_count;
+            node_count = node_count.div_ceil(BRANCHES);
+            node_index /= BRANCHES;
+        }
+
+        Some(proof)
+    }
+
+    /// Updates the data item at `item_index`, recomputing only the `O(log n)` nodes on the
+    /// path from the changed leaf to the root, and returns the new root hash.
+    pub fn update_item(&mut self, item_index: usize, new_data: &[u8]) -> Option<Hasher::Hash> {
+        if item_index >= self.item_count {
+            return None;
+        }
+
+        self.nodes.set(item_index, Hasher::hash(new_data));
+
+        let mut level_start_index = 0;
+        let mut node_count = self.item_count; // node count in level
+        let mut node_index = item_index; // node index in level
+
+        while node_count > 1 {
+            let (group_start_index, group_end_index) = Self::group_bounds(node_index, node_count);
+            let last_index_in_group = group_end_index - 1;
+
+            let group: Vec<Hasher::Hash> = (group_start_index..group_start_index + BRANCHES)
+                .map(|node_index| {
+                    self.node(level_start_index + node_index.min(last_index_in_group))
+                })
+                .collect();
+
+            let next_level_start_index = level_start_index + node_count;
+            let parent_index = next_level_start_index + node_index / BRANCHES;
+
+            self.nodes.set(parent_index, Hasher::concat_many(&group));
+
+            level_start_index = next_level_start_index;
+            node_count = node_count.div_ceil(BRANCHES);
+            node_index /= BRANCHES;
+        }
+
+        self.get_root()
+    }
+
+    fn calc_proof_hash(item_hash: Hasher::Hash, proof: &Proof<Hasher>) -> Hasher::Hash {
+        proof
+            .iter()
+            .fold(item_hash, |proof_hash, (siblings, position_in_group)| {
+                let mut group = siblings.clone();
+                group.insert(*position_in_group, proof_hash);
+                Hasher::concat_many(&group)
+            })
+    }
+
+    pub fn verify_proof(item_data: &[u8], root_hash: Hasher::Hash, proof: &Proof<Hasher>) -> bool {
+        let proof_hash = Self::calc_proof_hash(Hasher::hash(item_data), proof);
+        proof_hash == root_hash
+    }
+
+    /// Builds a single proof covering every leaf in `indices`, deduplicating sibling hashes
+    /// shared between them instead of concatenating one independent [`Proof`] per leaf.
+    pub fn get_multiproof(&self, indices: &[usize]) -> Option<MultiProof<Hasher>> {
+        if indices.iter().any(|&index| index >= self.item_count) {
+            return None;
+        }
+
+        let mut known: BTreeSet<usize> = indices.iter().copied().collect();
+        let mut levels = Vec::with_capacity(self.level_count.saturating_sub(1));
+
+        let mut level_start_index = 0;
+        let mut node_count = self.item_count; // node count in level
+
+        while node_count > 1 {
+            let mut siblings_at_level = Vec::new();
+            let mut next_known = BTreeSet::new();
+
+            let mut group_start_index = 0;
+            while group_start_index < node_count {
+                let group_end_index = (group_start_index + BRANCHES).min(node_count);
+
+                // A group only matters if at least one of its members is known; otherwise
+                // neither it nor its parent is on the path to any of the requested leaves.
+                if (group_start_index..group_end_index).any(|index| known.contains(&index)) {
+                    next_known.insert(group_start_index / BRANCHES);
+
+                    for index in group_start_index..group_end_index {
+                        if !known.contains(&index) {
+                            siblings_at_level.push(self.node(level_start_index + index));
+                        }
+                    }
+                }
+
+                group_start_index = group_end_index;
+            }
+
+            levels.push(siblings_at_level);
+            known = next_known;
+
+            level_start_index += node_count;
+            node_count = node_count.div_ceil(BRANCHES);
+        }
+
+        Some((self.item_count, levels))
+    }
+
+    /// Verifies a [`MultiProof`] against the given `(item_index, item_hash)` leaves, replaying
+    /// the same bottom-up, group-by-group walk used to build it.
+    pub fn verify_multiproof(
+        leaves: &[(usize, Hasher::Hash)],
+        root_hash: Hasher::Hash,
+        proof: &MultiProof<Hasher>,
+    ) -> bool {
+        let (item_count, levels) = proof;
+
+        if leaves.is_empty() || leaves.iter().any(|&(index, _)| index >= *item_count) {
+            return false;
+        }
+
+        let mut known: BTreeMap<usize, Hasher::Hash> = leaves.iter().copied().collect();
+        let mut node_count = *item_count;
+
+        for siblings_at_level in levels {
+            let mut next_known = BTreeMap::new();
+            let mut siblings = siblings_at_level.iter();
+
+            let mut group_start_index = 0;
+            while group_start_index < node_count {
+                let group_end_index = (group_start_index + BRANCHES).min(node_count);
+
+                if (group_start_index..group_end_index).any(|index| known.contains_key(&index)) {
+                    let mut group = Vec::with_capacity(group_end_index - group_start_index);
+
+                    for index in group_start_index..group_end_index {
+                        let hash = match known.get(&index) {
+                            Some(&hash) => hash,
+                            None => match siblings.next() {
+                                Some(&hash) => hash,
+                                None => return false,
+                            },
+                        };
+                        group.push(hash);
+                    }
+
+                    if let Some(&last) = group.last() {
+                        group.resize(BRANCHES, last);
+                    }
+
+                    next_known.insert(group_start_index / BRANCHES, Hasher::concat_many(&group));
+                }
+
+                group_start_index = group_end_index;
+            }
+
+            // Any sibling hash left unconsumed means this proof doesn't match the tree shape
+            // implied by `item_count` and `BRANCHES`.
+            if siblings.next().is_some() {
+                return false;
+            }
+
+            known = next_known;
+            node_count = node_count.div_ceil(BRANCHES);
+        }
+
+        known.get(&0) == Some(&root_hash)
+    }
+}
+
+impl<Hasher, S, const BRANCHES: usize> MerkleTree<Hasher, S, BRANCHES>
+where
+    Hasher: MerkleTreeHasher,
+    S: Store<Hasher::Hash> + Default,
+{
+    pub fn try_from_hash_items<Error, Items>(hash_items: Items) -> Result<Self, Error>
+    where
+        Items: IntoIterator<Item = Result<Hasher::Hash, Error>>,
+        <Items as IntoIterator>::IntoIter: ExactSizeIterator,
+    {
+        Self::try_from_hash_items_with_store(hash_items, S::default())
+    }
+
     pub fn from_hash_items<Items>(hash_items: Items) -> Self
     where
         Items: IntoIterator<Item = Hasher::Hash>,
@@ -143,55 +423,206 @@ impl<Hasher: MerkleTreeHasher> MerkleTree<Hasher> {
     {
         Self::try_from_data_items(data_items.into_iter().map(Ok::<_, ()>)).unwrap()
     }
+}
 
-    pub fn get_root(&self) -> Option<Hasher::Hash> {
-        self.nodes.last().copied()
+impl<Hasher, S, const BRANCHES: usize> MerkleTree<Hasher, S, BRANCHES>
+where
+    Hasher: MerkleTreeHasher,
+    Hasher::Hash: FixedSizeHash,
+    S: Store<Hasher::Hash>,
+{
+    /// Writes the tree to `writer` in a versioned, length-prefixed format: a version byte, the
+    /// hash width, the arity, the item count, and then every node's raw hash bytes in the same
+    /// reversed-breadth-first order they're stored in.
+    pub fn write_to<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(&[FORMAT_VERSION])?;
+        writer.write_all(&(Hasher::Hash::SIZE as u32).to_le_bytes())?;
+        writer.write_all(&(BRANCHES as u64).to_le_bytes())?;
+        writer.write_all(&(self.item_count as u64).to_le_bytes())?;
+
+        for index in 0..self.nodes.len() {
+            writer.write_all(&self.node(index).to_bytes())?;
+        }
+
+        Ok(())
     }
 
-    pub fn get_proof(&self, item_index: usize) -> Option<Proof<Hasher>> {
-        if item_index >= self.item_count {
-            return None;
+    /// Writes a `Proof` to `writer`: a version byte, the hash width, the arity, and the step
+    /// count, followed by each step's position-in-group packed into a bitmap (using the fewest
+    /// bits that can address `BRANCHES` positions) and then the raw sibling hash bytes.
+    pub fn write_proof_to<W: Write>(proof: &Proof<Hasher>, mut writer: W) -> io::Result<()> {
+        writer.write_all(&[FORMAT_VERSION])?;
+        writer.write_all(&(Hasher::Hash::SIZE as u32).to_le_bytes())?;
+        writer.write_all(&(BRANCHES as u64).to_le_bytes())?;
+        writer.write_all(&(proof.len() as u64).to_le_bytes())?;
+
+        let positions: Vec<usize> = proof.iter().map(|&(_, position)| position).collect();
+        writer.write_all(&pack_bits(&positions, Self::position_bits()))?;
+
+        for (siblings, _) in proof {
+            for sibling in siblings {
+                writer.write_all(&sibling.to_bytes())?;
+            }
         }
-        let mut proof = Proof::<Hasher>::with_capacity(self.level_count - 1);
 
-        let mut level_start_index = 0;
-        let mut node_count = self.item_count; // node count in level
-        let mut node_index = item_index; // node index in level
+        Ok(())
+    }
 
-        while node_count > 1 {
-            let sibling_node_index = (node_index ^ 1).min(node_count - 1);
+    /// Reads a `Proof` previously written by [`Self::write_proof_to`], rejecting a format
+    /// version, hash width, or arity it doesn't recognize.
+    pub fn read_proof_from<R: Read>(mut reader: R) -> io::Result<Proof<Hasher>> {
+        read_format_header(&mut reader, Hasher::Hash::SIZE, BRANCHES)?;
 
-            proof.push((
-                self.nodes[level_start_index + sibling_node_index],
-                sibling_node_index > node_index,
-            ));
+        let mut step_count_bytes = [0u8; 8];
+        reader.read_exact(&mut step_count_bytes)?;
+        let step_count = u64::from_le_bytes(step_count_bytes) as usize;
 
-            level_start_index += node_count;
-            node_count = (node_count + 1) >> 1;
-            node_index >>= 1;
+        let positions = unpack_bits(&mut reader, step_count, Self::position_bits())?;
+
+        let mut proof = Proof::<Hasher>::with_capacity(step_count);
+        let mut hash_bytes = vec![0u8; Hasher::Hash::SIZE];
+
+        for position in positions {
+            // position_bits() rounds up to the nearest bit, so for a non-power-of-two BRANCHES
+            // it can decode a value that's in range for the bitmap but not for an actual group.
+            if position >= BRANCHES {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "MerkleTree: decoded proof position is out of range for BRANCHES",
+                ));
+            }
+
+            let mut siblings = Vec::with_capacity(BRANCHES - 1);
+
+            for _ in 0..BRANCHES - 1 {
+                reader.read_exact(&mut hash_bytes)?;
+                siblings.push(Hasher::Hash::from_bytes(&hash_bytes));
+            }
+
+            proof.push((siblings, position));
         }
 
-        Some(proof)
+        Ok(proof)
     }
 
-    fn calc_proof_hash(item_hash: Hasher::Hash, proof: &Proof<Hasher>) -> Hasher::Hash {
-        proof
-            .iter()
-            .fold(item_hash, |proof_hash, (sibling_hash, is_right_sibling)| {
-                if *is_right_sibling {
-                    Hasher::concat(proof_hash, *sibling_hash)
-                } else {
-                    Hasher::concat(*sibling_hash, proof_hash)
-                }
-            })
+    // The number of bits needed to pack a position-in-group value (0..BRANCHES-1).
+    fn position_bits() -> u32 {
+        usize::BITS - (BRANCHES - 1).leading_zeros()
     }
+}
 
-    pub fn verify_proof(item_data: &[u8], root_hash: Hasher::Hash, proof: &Proof<Hasher>) -> bool {
-        let proof_hash = Self::calc_proof_hash(Hasher::hash(item_data), proof);
-        proof_hash == root_hash
+impl<Hasher, S, const BRANCHES: usize> MerkleTree<Hasher, S, BRANCHES>
+where
+    Hasher: MerkleTreeHasher,
+    Hasher::Hash: FixedSizeHash,
+    S: Store<Hasher::Hash> + Default,
+{
+    /// Reads a tree previously written by [`Self::write_to`], rejecting a format version, hash
+    /// width, or arity it doesn't recognize.
+    pub fn read_from<R: Read>(mut reader: R) -> io::Result<Self> {
+        read_format_header(&mut reader, Hasher::Hash::SIZE, BRANCHES)?;
+
+        let mut item_count_bytes = [0u8; 8];
+        reader.read_exact(&mut item_count_bytes)?;
+        let item_count = u64::from_le_bytes(item_count_bytes) as usize;
+
+        let (node_count, level_count) = Self::calc_tree_size(item_count);
+
+        let mut store = S::default();
+        let mut hash_bytes = vec![0u8; Hasher::Hash::SIZE];
+
+        for _ in 0..node_count {
+            reader.read_exact(&mut hash_bytes)?;
+            store.push(Hasher::Hash::from_bytes(&hash_bytes));
+        }
+
+        Ok(Self {
+            item_count,
+            level_count,
+            nodes: store,
+            _hasher: PhantomData,
+        })
     }
 }
 
+// Reads and validates the version byte, hash width, and arity shared by both the tree and
+// proof wire formats.
+fn read_format_header<R: Read>(
+    reader: &mut R,
+    hash_width: usize,
+    branches: usize,
+) -> io::Result<()> {
+    let mut version = [0u8; 1];
+    reader.read_exact(&mut version)?;
+    if version[0] != FORMAT_VERSION {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("MerkleTree: unsupported format version {}", version[0]),
+        ));
+    }
+
+    let mut hash_width_bytes = [0u8; 4];
+    reader.read_exact(&mut hash_width_bytes)?;
+    if u32::from_le_bytes(hash_width_bytes) as usize != hash_width {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "MerkleTree: hash width does not match this tree's hasher",
+        ));
+    }
+
+    let mut branches_bytes = [0u8; 8];
+    reader.read_exact(&mut branches_bytes)?;
+    if u64::from_le_bytes(branches_bytes) as usize != branches {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "MerkleTree: arity does not match this tree's BRANCHES",
+        ));
+    }
+
+    Ok(())
+}
+
+// Packs `values` (each less than `2^bits_per_value`) into a bitmap, LSB-first within each value.
+fn pack_bits(values: &[usize], bits_per_value: u32) -> Vec<u8> {
+    let total_bits = values.len() * bits_per_value as usize;
+    let mut bytes = vec![0u8; total_bits.div_ceil(8)];
+
+    for (index, &value) in values.iter().enumerate() {
+        for bit in 0..bits_per_value {
+            if (value >> bit) & 1 == 1 {
+                let bit_index = index * bits_per_value as usize + bit as usize;
+                bytes[bit_index / 8] |= 1 << (bit_index % 8);
+            }
+        }
+    }
+
+    bytes
+}
+
+// The inverse of `pack_bits`: reads the bitmap for `count` values of `bits_per_value` bits each.
+fn unpack_bits<R: Read>(
+    reader: &mut R,
+    count: usize,
+    bits_per_value: u32,
+) -> io::Result<Vec<usize>> {
+    let total_bits = count * bits_per_value as usize;
+    let mut bytes = vec![0u8; total_bits.div_ceil(8)];
+    reader.read_exact(&mut bytes)?;
+
+    Ok((0..count)
+        .map(|index| {
+            (0..bits_per_value).fold(0usize, |value, bit| {
+                let bit_index = index * bits_per_value as usize + bit as usize;
+                if bytes[bit_index / 8] & (1 << (bit_index % 8)) != 0 {
+                    value | (1 << bit)
+                } else {
+                    value
+                }
+            })
+        })
+        .collect())
+}
+
 #[test]
 fn test() {
     const LOREM_IPSUM: &str = "Lorem ipsum dolor sit amet, consectetur
@@ -227,3 +658,206 @@ fn test() {
         assert!(!Tree::verify_proof(wrong_item, root_hash, &proof));
     }
 }
+
+#[test]
+fn test_update_item() {
+    type Tree = MerkleTree<crate::hasher::SdbmHasher>;
+
+    let items = ["hello", "world", "foo", "bar", "baz"];
+
+    for item_count in 1..=items.len() {
+        let mut tree = Tree::from_data_items(items[..item_count].iter());
+
+        for item_index in 0..item_count {
+            let mut updated_items = items[..item_count].to_vec();
+            updated_items[item_index] = "updated";
+
+            let expected_root = Tree::from_data_items(updated_items.iter())
+                .get_root()
+                .unwrap();
+
+            let new_root = tree.update_item(item_index, "updated".as_bytes()).unwrap();
+
+            assert_eq!(new_root, expected_root);
+            assert_eq!(tree.get_root().unwrap(), expected_root);
+
+            let proof = tree.get_proof(item_index).unwrap();
+            assert!(Tree::verify_proof(b"updated", new_root, &proof));
+
+            // Revert so the next iteration starts from the original data again.
+            tree.update_item(item_index, items[item_index].as_bytes());
+        }
+    }
+
+    let mut tree = Tree::from_data_items(items.iter());
+    assert_eq!(tree.update_item(items.len(), b"oops"), None);
+}
+
+#[test]
+fn test_arity() {
+    let items = ["hello", "world", "foo", "bar", "baz"];
+
+    for item_count in 1..=items.len() {
+        type Tree2 =
+            MerkleTree<crate::hasher::SdbmHasher, crate::store::VecStore<crate::hasher::Hash64>, 2>;
+        type Tree4 =
+            MerkleTree<crate::hasher::SdbmHasher, crate::store::VecStore<crate::hasher::Hash64>, 4>;
+
+        let tree2 = Tree2::from_data_items(items[..item_count].iter());
+        let tree4 = Tree4::from_data_items(items[..item_count].iter());
+
+        let root2 = tree2.get_root().unwrap();
+        let root4 = tree4.get_root().unwrap();
+
+        for (item_index, item_data) in items[..item_count].iter().enumerate() {
+            let proof2 = tree2.get_proof(item_index).unwrap();
+            assert!(Tree2::verify_proof(item_data.as_bytes(), root2, &proof2));
+
+            let proof4 = tree4.get_proof(item_index).unwrap();
+            assert!(Tree4::verify_proof(item_data.as_bytes(), root4, &proof4));
+
+            // A wider tree should never need a longer proof than a binary one.
+            assert!(proof4.len() <= proof2.len());
+        }
+    }
+}
+
+#[test]
+fn test_multiproof() {
+    type Tree = MerkleTree<crate::hasher::SdbmHasher>;
+
+    let items = ["hello", "world", "foo", "bar", "baz", "qux", "quux"];
+
+    for item_count in 1..=items.len() {
+        let tree = Tree::from_data_items(items[..item_count].iter());
+        let root_hash = tree.get_root().unwrap();
+
+        for indices in [
+            (0..item_count).collect::<Vec<_>>(),
+            (0..item_count).step_by(2).collect::<Vec<_>>(),
+            vec![item_count - 1],
+        ] {
+            let proof = tree.get_multiproof(&indices).unwrap();
+
+            let leaves: Vec<(usize, crate::hasher::Hash64)> = indices
+                .iter()
+                .map(|&index| {
+                    (
+                        index,
+                        crate::hasher::SdbmHasher::hash(items[index].as_bytes()),
+                    )
+                })
+                .collect();
+
+            assert!(Tree::verify_multiproof(&leaves, root_hash, &proof));
+
+            // A single wrong leaf hash must invalidate the whole multiproof.
+            let mut wrong_leaves = leaves.clone();
+            wrong_leaves[0].1 = crate::hasher::SdbmHasher::hash(b"fake data");
+            assert!(!Tree::verify_multiproof(&wrong_leaves, root_hash, &proof));
+        }
+    }
+
+    let tree = Tree::from_data_items(items.iter());
+    assert_eq!(tree.get_multiproof(&[items.len()]), None);
+}
+
+#[test]
+fn test_serialization() {
+    type Tree =
+        MerkleTree<crate::hasher::SdbmHasher, crate::store::VecStore<crate::hasher::Hash64>, 4>;
+
+    let items = ["hello", "world", "foo", "bar", "baz"];
+
+    let tree = Tree::from_data_items(items.iter());
+    let root_hash = tree.get_root().unwrap();
+
+    let mut tree_bytes = Vec::new();
+    tree.write_to(&mut tree_bytes).unwrap();
+
+    let read_tree = Tree::read_from(tree_bytes.as_slice()).unwrap();
+    assert_eq!(read_tree.get_root().unwrap(), root_hash);
+
+    for (item_index, item_data) in items.iter().enumerate() {
+        let proof = tree.get_proof(item_index).unwrap();
+
+        let mut proof_bytes = Vec::new();
+        Tree::write_proof_to(&proof, &mut proof_bytes).unwrap();
+
+        let read_proof = Tree::read_proof_from(proof_bytes.as_slice()).unwrap();
+        assert_eq!(read_proof, proof);
+        assert!(Tree::verify_proof(
+            item_data.as_bytes(),
+            root_hash,
+            &read_proof
+        ));
+
+        // The reconstructed tree's proof must be identical to the original's.
+        let read_proof_from_tree = read_tree.get_proof(item_index).unwrap();
+        assert_eq!(read_proof_from_tree, proof);
+    }
+
+    // A version byte other than the one we write is rejected.
+    let mut bad_version_bytes = tree_bytes.clone();
+    bad_version_bytes[0] = 0xff;
+    assert!(Tree::read_from(bad_version_bytes.as_slice()).is_err());
+
+    // Reading a truncated buffer fails instead of panicking.
+    assert!(Tree::read_from(&tree_bytes[..tree_bytes.len() - 1]).is_err());
+}
+
+#[test]
+fn test_read_proof_rejects_out_of_range_position() {
+    // BRANCHES = 3 needs 2 bits per position (0..=2), but 2 bits can represent up to 3 - an
+    // attacker-flipped bitmap must be rejected rather than trusted into an out-of-bounds
+    // `Vec::insert` in `calc_proof_hash`.
+    type Tree =
+        MerkleTree<crate::hasher::SdbmHasher, crate::store::VecStore<crate::hasher::Hash64>, 3>;
+
+    let items = ["hello", "world", "foo", "bar", "baz"];
+    let tree = Tree::from_data_items(items.iter());
+
+    let proof = tree.get_proof(0).unwrap();
+    let mut proof_bytes = Vec::new();
+    Tree::write_proof_to(&proof, &mut proof_bytes).unwrap();
+
+    // The position bitmap immediately follows the 1-byte version, 4-byte hash width, 8-byte
+    // arity, and 8-byte step count header.
+    let bitmap_offset = 1 + 4 + 8 + 8;
+    proof_bytes[bitmap_offset] |= 0b11;
+
+    assert!(Tree::read_proof_from(proof_bytes.as_slice()).is_err());
+}
+
+#[test]
+fn test_disk_store() {
+    use crate::store::DiskStore;
+
+    type Tree = MerkleTree<crate::hasher::SdbmHasher, DiskStore<crate::hasher::Hash64>>;
+
+    let items = ["hello", "world", "foo", "bar", "baz"];
+
+    let path = std::env::temp_dir().join(format!(
+        "merkle_tree_test_disk_store_{:?}",
+        std::thread::current().id()
+    ));
+
+    let store = DiskStore::create(&path).unwrap();
+
+    let tree = Tree::try_from_hash_items_with_store(
+        items
+            .iter()
+            .map(|item| Ok::<_, ()>(crate::hasher::SdbmHasher::hash(item.as_bytes()))),
+        store,
+    )
+    .unwrap();
+
+    let root_hash = tree.get_root().unwrap();
+
+    for (item_index, item_data) in items.iter().enumerate() {
+        let proof = tree.get_proof(item_index).unwrap();
+        assert!(Tree::verify_proof(item_data.as_bytes(), root_hash, &proof));
+    }
+
+    std::fs::remove_file(&path).ok();
+}